@@ -0,0 +1,53 @@
+//! Host configuration, loaded from `fastly.toml` (or a plain JSON file with
+//! the same shape) at startup.
+//!
+//! Each subsystem (backends, dictionaries, geo, logging, ...) owns its own
+//! section of the config and its own `FooConfig` struct; this module just
+//! defines the top-level document and how to find/parse it.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::backend::BackendConfig;
+use crate::dictionary::DictionariesConfig;
+use crate::geo::GeoConfig;
+use crate::logging::LogConfig;
+
+/// The host-level configuration document.
+///
+/// Unknown tables are ignored so that a real `fastly.toml` (which carries a
+/// lot of fields the host doesn't care about, like `name` and `service_id`)
+/// can be pointed at directly.
+#[derive(Debug, Default, Deserialize)]
+pub struct HostConfig {
+    #[serde(default)]
+    pub backends: HashMap<String, BackendConfig>,
+    #[serde(default)]
+    pub geo: GeoConfig,
+    #[serde(flatten)]
+    pub dictionaries: DictionariesConfig,
+    #[serde(flatten)]
+    pub log: LogConfig,
+    /// Path to a uap-core `regexes.yaml` ruleset for `/user-agent` parsing.
+    pub uap_regexes: Option<std::path::PathBuf>,
+}
+
+impl HostConfig {
+    /// Load a config document from `path`, dispatching on its extension.
+    ///
+    /// `.json` files are parsed as JSON; everything else (including the
+    /// conventional `fastly.toml`) is parsed as TOML.
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("reading config at {}: {}", path.display(), e))?;
+
+        if path.extension().and_then(|e| e.to_str()) == Some("json") {
+            Ok(serde_json::from_str(&contents)?)
+        } else {
+            Ok(toml::from_str(&contents)?)
+        }
+    }
+}
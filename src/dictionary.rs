@@ -0,0 +1,142 @@
+//! Edge dictionaries, loaded from files named in the `[dictionaries]` table
+//! of host config.
+//!
+//! Each entry maps a dictionary name to a JSON file of string -> string
+//! pairs. Contents are cached in memory and refreshed whenever the file's
+//! mtime changes, so local iteration on a dictionary file doesn't require
+//! restarting the host.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use serde::Deserialize;
+
+use crate::error::XqdError;
+use crate::handles::Handles;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DictionariesConfig {
+    #[serde(default)]
+    pub dictionaries: HashMap<String, PathBuf>,
+}
+
+struct Loaded {
+    path: PathBuf,
+    modified: SystemTime,
+    contents: HashMap<String, String>,
+}
+
+impl Loaded {
+    fn load(path: PathBuf) -> anyhow::Result<Self> {
+        let modified = std::fs::metadata(&path)?.modified()?;
+        let contents = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+        Ok(Loaded { path, modified, contents })
+    }
+
+    /// Re-read the file if its mtime has moved on since we last loaded it.
+    fn refresh_if_stale(&mut self) {
+        let modified = match std::fs::metadata(&self.path).and_then(|m| m.modified()) {
+            Ok(modified) => modified,
+            Err(_) => return,
+        };
+        if modified == self.modified {
+            return;
+        }
+        if let Ok(contents) = std::fs::read_to_string(&self.path)
+            .map_err(anyhow::Error::from)
+            .and_then(|s| Ok(serde_json::from_str(&s)?))
+        {
+            self.contents = contents;
+            self.modified = modified;
+        }
+    }
+}
+
+/// Shared across all sessions: the loaded-from-disk contents of every
+/// configured dictionary, keyed by name.
+pub struct Dictionaries {
+    loaded: HashMap<String, Mutex<Loaded>>,
+}
+
+impl Dictionaries {
+    pub fn from_config(config: DictionariesConfig) -> anyhow::Result<Self> {
+        let mut loaded = HashMap::with_capacity(config.dictionaries.len());
+        for (name, path) in config.dictionaries {
+            loaded.insert(name, Mutex::new(Loaded::load(path)?));
+        }
+        Ok(Dictionaries { loaded })
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        self.loaded.contains_key(name)
+    }
+
+    /// Look up `key` in dictionary `name`, refreshing from disk first if the
+    /// file has changed since it was last read.
+    fn get(&self, name: &str, key: &str) -> Option<String> {
+        let mut loaded = self.loaded.get(name)?.lock().unwrap();
+        loaded.refresh_if_stale();
+        loaded.contents.get(key).cloned()
+    }
+}
+
+/// Per-session table of dictionary handles the guest has opened, mapping
+/// each handle back to the dictionary name it was opened with.
+#[derive(Default)]
+pub struct OpenDictionaries {
+    handles: Handles<String>,
+}
+
+impl OpenDictionaries {
+    /// `xqd_dictionary_open`: hand back a handle for `name`, or
+    /// [`XqdError::NotFound`] if it isn't in the `[dictionaries]` table.
+    pub fn open(&mut self, dictionaries: &Dictionaries, name: &str) -> Result<u32, XqdError> {
+        if !dictionaries.contains(name) {
+            return Err(XqdError::NotFound);
+        }
+        Ok(self.handles.insert(name.to_string()))
+    }
+
+    /// `xqd_dictionary_get`: look up `key` in the dictionary behind
+    /// `handle`.
+    pub fn get(&self, dictionaries: &Dictionaries, handle: u32, key: &str) -> Result<Option<String>, XqdError> {
+        let name = self.handles.get(handle).ok_or(XqdError::BadHandle)?;
+        Ok(dictionaries.get(name, key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_dictionary(contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("fastlike-dictionary-test-{:?}.json", std::thread::current().id()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn unknown_dictionary_name_is_not_found() {
+        let dictionaries = Dictionaries::from_config(DictionariesConfig::default()).unwrap();
+        let mut opened = OpenDictionaries::default();
+        assert_eq!(opened.open(&dictionaries, "nope"), Err(XqdError::NotFound));
+    }
+
+    #[test]
+    fn missing_key_returns_none_not_an_error() {
+        let path = write_dictionary(r#"{"present": "value"}"#);
+        let mut config = DictionariesConfig::default();
+        config.dictionaries.insert("animals".to_string(), path.clone());
+        let dictionaries = Dictionaries::from_config(config).unwrap();
+
+        let mut opened = OpenDictionaries::default();
+        let handle = opened.open(&dictionaries, "animals").unwrap();
+
+        assert_eq!(opened.get(&dictionaries, handle, "present").unwrap(), Some("value".to_string()));
+        assert_eq!(opened.get(&dictionaries, handle, "missing").unwrap(), None);
+
+        std::fs::remove_file(path).ok();
+    }
+}
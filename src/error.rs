@@ -0,0 +1,44 @@
+//! Error surface shared by the hostcall implementations.
+//!
+//! The guest-facing `fastly` crate expects hostcalls to return one of a small
+//! set of well-known XQD status codes rather than an arbitrary error string,
+//! so every subsystem maps its internal errors onto [`XqdError`] at the
+//! hostcall boundary.
+
+use std::fmt;
+
+/// Status codes surfaced back to the guest across the xqd ABI.
+///
+/// These mirror the `FastlyStatus` values the `fastly` crate matches on; we
+/// only grow this enum as hostcalls need to distinguish new failure modes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum XqdError {
+    Ok = 0,
+    GenericError = 1,
+    InvalidArgument = 2,
+    /// `req.send("some-backend")` named a backend that isn't in the registry.
+    UnknownBackend = 3,
+    /// A handle (pending request, dictionary, log endpoint, ...) wasn't found
+    /// or was already consumed.
+    BadHandle = 4,
+    /// A named resource (e.g. a dictionary that isn't in the
+    /// `[dictionaries]` table) doesn't exist.
+    NotFound = 5,
+}
+
+impl fmt::Display for XqdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            XqdError::Ok => "ok",
+            XqdError::GenericError => "generic error",
+            XqdError::InvalidArgument => "invalid argument",
+            XqdError::UnknownBackend => "unknown backend",
+            XqdError::BadHandle => "bad handle",
+            XqdError::NotFound => "not found",
+        };
+        f.write_str(msg)
+    }
+}
+
+impl std::error::Error for XqdError {}
@@ -0,0 +1,32 @@
+//! A small generic handle table.
+//!
+//! The xqd ABI hands the guest opaque `u32` handles for requests, responses,
+//! bodies, dictionaries, log endpoints, pending requests, and so on. Requests,
+//! responses, dictionaries and log endpoints store their values in one of
+//! these; pending requests keep their own table in `pending`.
+
+use std::collections::HashMap;
+
+pub struct Handles<T> {
+    next: u32,
+    entries: HashMap<u32, T>,
+}
+
+impl<T> Default for Handles<T> {
+    fn default() -> Self {
+        Handles { next: 0, entries: HashMap::new() }
+    }
+}
+
+impl<T> Handles<T> {
+    pub fn insert(&mut self, value: T) -> u32 {
+        let handle = self.next;
+        self.next += 1;
+        self.entries.insert(handle, value);
+        handle
+    }
+
+    pub fn get(&self, handle: u32) -> Option<&T> {
+        self.entries.get(&handle)
+    }
+}
@@ -0,0 +1,212 @@
+//! fastlike host: local implementations of the Fastly Compute@Edge xqd
+//! hostcalls, backing the guest-facing `fastly` crate used by `testdata` and
+//! the other example programs in this repo.
+
+mod backend;
+mod config;
+mod dictionary;
+mod error;
+mod geo;
+mod handles;
+mod logging;
+mod pending;
+mod uap;
+
+pub use config::HostConfig;
+pub use error::XqdError;
+pub use geo::GeoRecord;
+pub use uap::UserAgent;
+
+use backend::BackendRegistry;
+use dictionary::{Dictionaries, OpenDictionaries};
+use geo::GeoLookup;
+use handles::Handles;
+use logging::{LogEndpoints, OpenEndpoints};
+use pending::{Pool, PendingRequests};
+use uap::UapParser;
+
+/// Per-request host state: everything a single invocation of the guest
+/// program can see and mutate via hostcalls.
+///
+/// A fresh `Session` is built for every downstream request; the registries
+/// that are expensive to construct (backends, dictionaries, geo database,
+/// ...) live on [`Runtime`] and are shared across sessions instead.
+pub struct Session {
+    pub(crate) runtime: std::sync::Arc<Runtime>,
+    pub(crate) requests: Handles<http::Request<Vec<u8>>>,
+    pub(crate) responses: Handles<http::Response<Vec<u8>>>,
+    pub(crate) pending: PendingRequests,
+    pub(crate) dictionaries: OpenDictionaries,
+    pub(crate) log_endpoints: OpenEndpoints,
+}
+
+/// Host state shared across all sessions for the lifetime of the process.
+pub struct Runtime {
+    pub(crate) backends: BackendRegistry,
+    pub(crate) geo: GeoLookup,
+    pub(crate) dictionaries: Dictionaries,
+    pub(crate) log_endpoints: LogEndpoints,
+    pub(crate) uap: UapParser,
+    pub(crate) pool: Pool,
+}
+
+/// Worker threads kept warm for `xqd_req_send_async` fetches.
+const ASYNC_POOL_SIZE: usize = 8;
+
+impl Runtime {
+    pub fn new(config: HostConfig) -> anyhow::Result<Self> {
+        let uap_regexes = match &config.uap_regexes {
+            Some(path) => std::fs::read_to_string(path)?,
+            None => "user_agent_parsers: []".to_string(),
+        };
+
+        Ok(Runtime {
+            backends: BackendRegistry::from_config(config.backends)?,
+            geo: GeoLookup::from_config(config.geo)?,
+            dictionaries: Dictionaries::from_config(config.dictionaries)?,
+            log_endpoints: LogEndpoints::from_config(config.log)?,
+            uap: UapParser::from_regexes_yaml(&uap_regexes)?,
+            pool: Pool::new(ASYNC_POOL_SIZE),
+        })
+    }
+}
+
+impl Session {
+    pub fn new(runtime: std::sync::Arc<Runtime>) -> Self {
+        Session {
+            runtime,
+            requests: Handles::default(),
+            responses: Handles::default(),
+            pending: PendingRequests::default(),
+            dictionaries: OpenDictionaries::default(),
+            log_endpoints: OpenEndpoints::default(),
+        }
+    }
+
+    /// `xqd_req_send`: resolve `backend_name` through the backend registry,
+    /// issue a blocking request to its origin carrying `req_handle`'s
+    /// method/headers/body, and return a fresh handle for the response.
+    pub fn xqd_req_send(
+        &mut self,
+        req_handle: u32,
+        backend_name: &str,
+    ) -> Result<u32, XqdError> {
+        let req = self
+            .requests
+            .get(req_handle)
+            .ok_or(XqdError::BadHandle)?;
+
+        let backend = self.runtime.backends.resolve(backend_name)?;
+
+        let path_and_query = req
+            .uri()
+            .path_and_query()
+            .map(|pq| pq.as_str())
+            .unwrap_or("/");
+
+        let resp = backend
+            .send(req.method().clone(), path_and_query, req.headers().clone(), req.body().clone())
+            .map_err(|_| XqdError::GenericError)?;
+
+        let response = materialize_response(resp).map_err(|_| XqdError::GenericError)?;
+        Ok(self.responses.insert(response))
+    }
+
+    /// `xqd_req_send_async`: like [`Session::xqd_req_send`], but the upstream
+    /// fetch runs on a worker thread and a pending-request handle is
+    /// returned immediately instead of blocking.
+    pub fn xqd_req_send_async(
+        &mut self,
+        req_handle: u32,
+        backend_name: &str,
+    ) -> Result<u32, XqdError> {
+        let req = self
+            .requests
+            .get(req_handle)
+            .ok_or(XqdError::BadHandle)?;
+
+        // Confirm the backend exists before spawning so unknown-backend
+        // errors surface synchronously, same as the blocking path.
+        self.runtime.backends.resolve(backend_name)?;
+
+        let method = req.method().clone();
+        let path_and_query = req
+            .uri()
+            .path_and_query()
+            .map(|pq| pq.as_str())
+            .unwrap_or("/")
+            .to_string();
+        let headers = req.headers().clone();
+        let body = req.body().clone();
+        let backend_name = backend_name.to_string();
+        let runtime = self.runtime.clone();
+
+        let handle = self.pending.spawn(&self.runtime.pool, move || {
+            let backend = runtime
+                .backends
+                .resolve(&backend_name)
+                .map_err(|e| anyhow::anyhow!(e))?;
+            let resp = backend.send(method, &path_and_query, headers, body)?;
+            materialize_response(resp)
+        });
+
+        Ok(handle)
+    }
+
+    /// `xqd_pending_req_poll`: non-blocking readiness check.
+    pub fn xqd_pending_req_poll(&mut self, pending_handle: u32) -> Result<bool, XqdError> {
+        self.pending.poll(pending_handle)
+    }
+
+    /// `xqd_geo_lookup`: resolve `ip` to a [`GeoRecord`] via the configured
+    /// MaxMind database, an IP override, or the default record.
+    pub fn xqd_geo_lookup(&self, ip: std::net::IpAddr) -> GeoRecord {
+        self.runtime.geo.lookup(ip)
+    }
+
+    /// `xqd_dictionary_open`: open the named dictionary for this session.
+    pub fn xqd_dictionary_open(&mut self, name: &str) -> Result<u32, XqdError> {
+        self.dictionaries.open(&self.runtime.dictionaries, name)
+    }
+
+    /// `xqd_dictionary_get`: look up `key` in a previously opened
+    /// dictionary. `Ok(None)` means the key isn't present, which the guest's
+    /// `Dictionary::get` surfaces as `None` rather than an error.
+    pub fn xqd_dictionary_get(&self, dict_handle: u32, key: &str) -> Result<Option<String>, XqdError> {
+        self.dictionaries.get(&self.runtime.dictionaries, dict_handle, key)
+    }
+
+    /// `xqd_log_endpoint_get`: hand back a handle for `name`. Unconfigured
+    /// names are accepted so guests never fail on a missing endpoint.
+    pub fn xqd_log_endpoint_get(&mut self, name: &str) -> u32 {
+        self.log_endpoints.get(name)
+    }
+
+    /// `xqd_log_write`: append `line` to the sink behind `handle`.
+    pub fn xqd_log_write(&self, endpoint_handle: u32, line: String) -> Result<(), XqdError> {
+        self.log_endpoints.write(&self.runtime.log_endpoints, endpoint_handle, line)
+    }
+
+    /// `xqd_uap_parse`: parse `ua` against the compiled uap-core ruleset.
+    pub fn xqd_uap_parse(&self, ua: &str) -> UserAgent {
+        self.runtime.uap.parse(ua)
+    }
+
+    /// `xqd_pending_req_select`: block until the first of `handles`
+    /// completes, returning its index and a fresh handle for the
+    /// materialized response.
+    pub fn xqd_pending_req_select(&mut self, handles: &[u32]) -> Result<(usize, u32), XqdError> {
+        let (index, result) = self.pending.select(handles)?;
+        let response = result.map_err(|_| XqdError::GenericError)?;
+        Ok((index, self.responses.insert(response)))
+    }
+}
+
+fn materialize_response(resp: reqwest::blocking::Response) -> anyhow::Result<http::Response<Vec<u8>>> {
+    let mut builder = http::Response::builder().status(resp.status());
+    for (name, value) in resp.headers() {
+        builder = builder.header(name, value);
+    }
+    let body = resp.bytes()?.to_vec();
+    Ok(builder.body(body)?)
+}
@@ -0,0 +1,125 @@
+//! Log endpoints: `xqd_log_endpoint_get` / `xqd_log_write`, backed by
+//! configurable sinks rather than a single stubbed-out writer.
+//!
+//! A sink is configured per endpoint name in the `[log_endpoints]` table of
+//! host config; any name the guest asks for that isn't configured falls
+//! back to stderr with a `[name]` prefix, so a guest never fails just
+//! because a log endpoint wasn't declared.
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::Deserialize;
+
+use crate::error::XqdError;
+use crate::handles::Handles;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SinkConfig {
+    Stdout,
+    Stderr,
+    File { path: PathBuf },
+    Tcp { address: String },
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct LogConfig {
+    #[serde(default)]
+    pub log_endpoints: HashMap<String, SinkConfig>,
+}
+
+enum Sink {
+    Stdout,
+    Stderr,
+    File(File),
+    Tcp(TcpStream),
+    /// An endpoint name with no configured sink: stderr, prefixed with the
+    /// endpoint's name so interleaved output stays attributable.
+    Unconfigured(String),
+}
+
+impl Sink {
+    fn from_config(config: &SinkConfig) -> anyhow::Result<Self> {
+        Ok(match config {
+            SinkConfig::Stdout => Sink::Stdout,
+            SinkConfig::Stderr => Sink::Stderr,
+            SinkConfig::File { path } => Sink::File(OpenOptions::new().create(true).append(true).open(path)?),
+            SinkConfig::Tcp { address } => Sink::Tcp(TcpStream::connect(address)?),
+        })
+    }
+
+    /// Write one already-newline-terminated log line.
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        match self {
+            Sink::Stdout => {
+                print!("{}", line);
+                std::io::stdout().flush()
+            }
+            Sink::Stderr => {
+                eprint!("{}", line);
+                std::io::stderr().flush()
+            }
+            Sink::File(file) => file.write_all(line.as_bytes()),
+            Sink::Tcp(stream) => stream.write_all(line.as_bytes()),
+            Sink::Unconfigured(name) => {
+                eprint!("[{}] {}", name, line);
+                std::io::stderr().flush()
+            }
+        }
+    }
+}
+
+/// Shared across all sessions: the sinks backing each configured endpoint
+/// name, plus any ad-hoc sinks created for unconfigured names.
+pub struct LogEndpoints {
+    sinks: Mutex<HashMap<String, Sink>>,
+}
+
+impl LogEndpoints {
+    pub fn from_config(config: LogConfig) -> anyhow::Result<Self> {
+        let mut sinks = HashMap::with_capacity(config.log_endpoints.len());
+        for (name, sink_config) in &config.log_endpoints {
+            sinks.insert(name.clone(), Sink::from_config(sink_config)?);
+        }
+        Ok(LogEndpoints { sinks: Mutex::new(sinks) })
+    }
+
+    /// Buffer-per-write, flush-on-newline: each `xqd_log_write` call is one
+    /// line, so every write is immediately flushed.
+    fn write(&self, name: &str, mut line: String) -> anyhow::Result<()> {
+        if !line.ends_with('\n') {
+            line.push('\n');
+        }
+        let mut sinks = self.sinks.lock().unwrap();
+        let sink = sinks
+            .entry(name.to_string())
+            .or_insert_with(|| Sink::Unconfigured(name.to_string()));
+        sink.write_line(&line)?;
+        Ok(())
+    }
+}
+
+/// Per-session table of endpoint handles the guest has opened.
+#[derive(Default)]
+pub struct OpenEndpoints {
+    handles: Handles<String>,
+}
+
+impl OpenEndpoints {
+    /// `xqd_log_endpoint_get`: unconfigured names are accepted too (they
+    /// fall back to a prefixed stderr sink), so this never fails.
+    pub fn get(&mut self, name: &str) -> u32 {
+        self.handles.insert(name.to_string())
+    }
+
+    /// `xqd_log_write`: append `line` to the sink behind `handle`.
+    pub fn write(&self, endpoints: &LogEndpoints, handle: u32, line: String) -> Result<(), XqdError> {
+        let name = self.handles.get(handle).ok_or(XqdError::BadHandle)?;
+        endpoints.write(name, line).map_err(|_| XqdError::GenericError)
+    }
+}
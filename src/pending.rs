@@ -0,0 +1,177 @@
+//! Pending request table backing `req.send_async` / `PendingRequest::select`.
+//!
+//! Each `xqd_req_send_async` hands the upstream fetch to a bounded worker
+//! pool and returns a handle the guest can later poll or select over.
+//! Completions are delivered over an mpsc channel rather than observed by
+//! polling a join handle: `poll` drains the channel without blocking, and
+//! `select` blocks on it instead of busy-spinning while a fetch is in flight.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+use crate::error::XqdError;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// A small fixed-size pool of worker threads that backend fetches are
+/// dispatched onto, so `send_async` doesn't spawn a fresh OS thread per
+/// call.
+pub struct Pool {
+    jobs: Sender<Job>,
+}
+
+impl Pool {
+    pub fn new(size: usize) -> Self {
+        let (jobs, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        for _ in 0..size {
+            let receiver = receiver.clone();
+            std::thread::spawn(move || loop {
+                let job = receiver.lock().unwrap().recv();
+                match job {
+                    Ok(job) => job(),
+                    Err(_) => return,
+                }
+            });
+        }
+
+        Pool { jobs }
+    }
+
+    fn execute<F: FnOnce() + Send + 'static>(&self, job: F) {
+        // The worker threads hold the receiving end and only exit once this
+        // sender is dropped, so it is still there while a `Pool` exists.
+        self.jobs.send(Box::new(job)).expect("worker pool is still running");
+    }
+}
+
+type BackendResult = anyhow::Result<http::Response<Vec<u8>>>;
+
+/// The table `xqd_pending_req_poll`/`xqd_pending_req_select` operate on, one
+/// per [`crate::Session`].
+pub struct PendingRequests {
+    next_handle: u32,
+    outstanding: HashSet<u32>,
+    ready: HashMap<u32, BackendResult>,
+    completions: Sender<(u32, BackendResult)>,
+    incoming: Receiver<(u32, BackendResult)>,
+}
+
+impl Default for PendingRequests {
+    fn default() -> Self {
+        let (completions, incoming) = mpsc::channel();
+        PendingRequests {
+            next_handle: 0,
+            outstanding: HashSet::new(),
+            ready: HashMap::new(),
+            completions,
+            incoming,
+        }
+    }
+}
+
+impl PendingRequests {
+    /// Dispatch `work` onto `pool` and return a fresh pending-request
+    /// handle for it.
+    pub fn spawn<F>(&mut self, pool: &Pool, work: F) -> u32
+    where
+        F: FnOnce() -> BackendResult + Send + 'static,
+    {
+        let handle = self.next_handle;
+        self.next_handle += 1;
+        self.outstanding.insert(handle);
+
+        let completions = self.completions.clone();
+        pool.execute(move || {
+            // The other end is held by this `PendingRequests`; if it has
+            // been dropped before the fetch finished, the result is discarded.
+            let _ = completions.send((handle, work()));
+        });
+
+        handle
+    }
+
+    fn drain_ready(&mut self) {
+        while let Ok((handle, result)) = self.incoming.try_recv() {
+            self.ready.insert(handle, result);
+        }
+    }
+
+    fn known(&self, handle: u32) -> bool {
+        self.outstanding.contains(&handle) || self.ready.contains_key(&handle)
+    }
+
+    /// `xqd_pending_req_poll`: true if `handle` is ready, without consuming
+    /// it either way.
+    pub fn poll(&mut self, handle: u32) -> Result<bool, XqdError> {
+        if !self.known(handle) {
+            return Err(XqdError::BadHandle);
+        }
+        self.drain_ready();
+        Ok(self.ready.contains_key(&handle))
+    }
+
+    /// `xqd_pending_req_select`: block until the first of `handles`
+    /// completes, returning its index in `handles` and its result. Blocks on
+    /// the completion channel rather than polling in a loop.
+    pub fn select(&mut self, handles: &[u32]) -> Result<(usize, BackendResult), XqdError> {
+        if handles.is_empty() {
+            return Err(XqdError::InvalidArgument);
+        }
+        for &handle in handles {
+            if !self.known(handle) {
+                return Err(XqdError::BadHandle);
+            }
+        }
+
+        loop {
+            self.drain_ready();
+            for (index, &handle) in handles.iter().enumerate() {
+                if let Some(result) = self.ready.remove(&handle) {
+                    self.outstanding.remove(&handle);
+                    return Ok((index, result));
+                }
+            }
+
+            // None of `handles` are ready yet; block for the next
+            // completion from any in-flight fetch instead of spinning.
+            match self.incoming.recv() {
+                Ok((handle, result)) => {
+                    self.ready.insert(handle, result);
+                }
+                Err(_) => return Err(XqdError::GenericError),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn select_over_empty_handles_is_invalid_argument() {
+        let mut pending = PendingRequests::default();
+        assert!(matches!(pending.select(&[]), Err(XqdError::InvalidArgument)));
+    }
+
+    #[test]
+    fn select_returns_the_first_completion() {
+        let pool = Pool::new(2);
+        let mut pending = PendingRequests::default();
+
+        let slow = pending.spawn(&pool, || {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            Ok(http::Response::builder().status(200).body(b"slow".to_vec()).unwrap())
+        });
+        let fast = pending.spawn(&pool, || {
+            Ok(http::Response::builder().status(200).body(b"fast".to_vec()).unwrap())
+        });
+
+        let (index, result) = pending.select(&[slow, fast]).unwrap();
+        assert_eq!(index, 1);
+        assert_eq!(result.unwrap().body(), b"fast");
+    }
+}
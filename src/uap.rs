@@ -0,0 +1,160 @@
+//! `xqd_uap_parse`, backed by the standard [uap-core] `regexes.yaml` ruleset
+//! instead of a stub.
+//!
+//! Rules are compiled once at startup and tried in order; the first match's
+//! capture groups are substituted into that rule's replacement templates
+//! (`$1`, `$2`, ...) to produce the family/major/minor/patch strings the
+//! guest's `uap_parse` returns. Recent results are memoized since request
+//! logs tend to repeat the same handful of user-agent strings heavily.
+//!
+//! [uap-core]: https://github.com/ua-parser/uap-core
+
+use std::sync::Mutex;
+
+use lru::LruCache;
+use regex::Regex;
+use serde::Deserialize;
+
+/// A single entry from `regexes.yaml`'s `user_agent_parsers` list.
+#[derive(Debug, Deserialize)]
+struct RawRule {
+    regex: String,
+    family_replacement: Option<String>,
+    v1_replacement: Option<String>,
+    v2_replacement: Option<String>,
+    v3_replacement: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawRules {
+    user_agent_parsers: Vec<RawRule>,
+}
+
+struct Rule {
+    regex: Regex,
+    family_replacement: Option<String>,
+    v1_replacement: Option<String>,
+    v2_replacement: Option<String>,
+    v3_replacement: Option<String>,
+}
+
+/// The parsed (family, major, minor, patch) tuple `xqd_uap_parse` returns.
+pub type UserAgent = (String, Option<String>, Option<String>, Option<String>);
+
+pub struct UapParser {
+    rules: Vec<Rule>,
+    cache: Mutex<LruCache<String, UserAgent>>,
+}
+
+impl UapParser {
+    /// Compile every rule in `regexes.yaml`'s contents, in order.
+    pub fn from_regexes_yaml(contents: &str) -> anyhow::Result<Self> {
+        let raw: RawRules = serde_yaml::from_str(contents)?;
+        let mut rules = Vec::with_capacity(raw.user_agent_parsers.len());
+        for raw_rule in raw.user_agent_parsers {
+            rules.push(Rule {
+                regex: Regex::new(&raw_rule.regex)?,
+                family_replacement: raw_rule.family_replacement,
+                v1_replacement: raw_rule.v1_replacement,
+                v2_replacement: raw_rule.v2_replacement,
+                v3_replacement: raw_rule.v3_replacement,
+            });
+        }
+        Ok(UapParser {
+            rules,
+            cache: Mutex::new(LruCache::new(std::num::NonZeroUsize::new(256).unwrap())),
+        })
+    }
+
+    /// `xqd_uap_parse`: walk the rules in order, returning the first
+    /// match's substituted fields, or `"Other"` with no version if nothing
+    /// matches.
+    pub fn parse(&self, ua: &str) -> UserAgent {
+        if let Some(cached) = self.cache.lock().unwrap().get(ua) {
+            return cached.clone();
+        }
+
+        let result = self.parse_uncached(ua);
+        self.cache.lock().unwrap().put(ua.to_string(), result.clone());
+        result
+    }
+
+    fn parse_uncached(&self, ua: &str) -> UserAgent {
+        for rule in &self.rules {
+            if let Some(captures) = rule.regex.captures(ua) {
+                let family = rule
+                    .family_replacement
+                    .as_ref()
+                    .map(|template| substitute(template, &captures))
+                    .unwrap_or_else(|| captures.get(1).map(|m| m.as_str().to_string()).unwrap_or_else(|| ua.to_string()));
+
+                let major = rule.v1_replacement.as_ref().map(|template| substitute(template, &captures))
+                    .or_else(|| captures.get(2).map(|m| m.as_str().to_string()));
+                let minor = rule.v2_replacement.as_ref().map(|template| substitute(template, &captures))
+                    .or_else(|| captures.get(3).map(|m| m.as_str().to_string()));
+                let patch = rule.v3_replacement.as_ref().map(|template| substitute(template, &captures))
+                    .or_else(|| captures.get(4).map(|m| m.as_str().to_string()));
+
+                return (family, major, minor, patch);
+            }
+        }
+
+        ("Other".to_string(), None, None, None)
+    }
+}
+
+/// Replace `$1`, `$2`, ... in `template` with the corresponding capture
+/// group, matching uap-core's replacement-string convention.
+fn substitute(template: &str, captures: &regex::Captures) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '$' {
+            let mut digits = String::new();
+            while let Some(d) = chars.peek() {
+                if d.is_ascii_digit() {
+                    digits.push(*d);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            if !digits.is_empty() {
+                if let Ok(index) = digits.parse::<usize>() {
+                    if let Some(m) = captures.get(index) {
+                        out.push_str(m.as_str());
+                    }
+                }
+                continue;
+            }
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_rule_match_falls_back_to_other() {
+        let parser = UapParser::from_regexes_yaml("user_agent_parsers: []").unwrap();
+        assert_eq!(parser.parse("anything"), ("Other".to_string(), None, None, None));
+    }
+
+    #[test]
+    fn matching_rule_fills_family_and_version_from_capture_groups() {
+        let yaml = r#"
+user_agent_parsers:
+  - regex: '(Chrome)/(\d+)\.(\d+)\.(\d+)'
+  - regex: '(Firefox)/(\d+)'
+"#;
+        let parser = UapParser::from_regexes_yaml(yaml).unwrap();
+        let (family, major, minor, patch) = parser.parse("Mozilla/5.0 Chrome/120.0.1");
+        assert_eq!(family, "Chrome");
+        assert_eq!(major, Some("120".to_string()));
+        assert_eq!(minor, Some("0".to_string()));
+        assert_eq!(patch, Some("1".to_string()));
+    }
+}
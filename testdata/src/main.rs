@@ -85,6 +85,27 @@ fn main(mut req: Request<Body>) -> Result<impl ResponseExt, Error> {
             )
         },
 
+        (&Method::GET, "/log") => {
+            use std::io::Write;
+            use fastly::log::Endpoint;
+            let mut endpoint = Endpoint::from_name("default");
+            writeln!(endpoint, "Hello from fastlike!").unwrap();
+            Ok(Response::builder()
+               .status(StatusCode::NO_CONTENT)
+               .body(Body::new()?)?)
+        },
+
+        (&Method::GET, path) if path.starts_with("/dictionary") => {
+            let parts: Vec<&str> = path[1..].split("/").collect();
+            let (name, key) = (parts[1], parts[2]);
+            use fastly::dictionary::Dictionary;
+            let dict = Dictionary::open(name);
+            let value = dict.get(key).unwrap();
+            Ok(Response::builder()
+               .status(StatusCode::OK)
+               .body(Body::try_from(value)?)?)
+        },
+
         // This one is used for example purposes, not tests
         (&Method::GET, path) if path.starts_with("/testdata") => {
             req.send(BACKEND)